@@ -0,0 +1,25 @@
+///
+/// Abstract, bindable actions. The render loop dispatches these instead of matching raw
+/// `VirtualKeyCode`/`Mpd218Message` variants, so remapping a controller or adding a new
+/// one is a `config` change rather than a code change. `Deserialize` lets `config.toml`
+/// spell these directly, e.g. `R = "RandomizePreset"` or `"2" = { LoadPreset = 1 }`.
+///
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub enum Action {
+    LoadPreset(u32),
+    LoadBeatPreset(u32),
+    RandomizePreset,
+    RandomizeBeatPreset,
+    RegeneratePoints,
+    ClearTextures,
+    SavePreset,
+    ToggleFullscreen,
+    TakeScreenshot,
+    ToggleRecording,
+    ToggleHud,
+    /// Scrub `u_time` directly to a normalized `0.0..=1.0` position on some input axis.
+    ScrubTime(f32),
+    Quit,
+}
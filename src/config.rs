@@ -0,0 +1,102 @@
+///
+/// Application configuration: audio/MIDI device selection, optional GNU Rocket/tracker
+/// driven playback, and the bindable input layout from `input::Bindings`.
+///
+use crate::input::{Bindings, FileBindings};
+use serde::Deserialize;
+use std::fs;
+
+const CONFIG_PATH: &str = "config.toml";
+
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+struct FileConfig {
+    midi_device_id: Option<u32>,
+    audio_host_name: Option<String>,
+    audio_device_id: Option<u32>,
+    module_path: Option<String>,
+    rocket_address: Option<String>,
+    rocket_bpm: Option<f64>,
+    rocket_rows_per_beat: Option<f64>,
+    headless: Option<bool>,
+    render_width: Option<u32>,
+    render_height: Option<u32>,
+    headless_frame_count: Option<u32>,
+    hud_font_path: Option<String>,
+    bindings: FileBindings,
+}
+
+pub struct AppConfig {
+    pub midi_device_id: Option<u32>,
+    pub audio_host_name: Option<String>,
+    pub audio_device_id: Option<u32>,
+    pub module_path: Option<String>,
+    pub rocket_address: Option<String>,
+    pub rocket_bpm: f64,
+    pub rocket_rows_per_beat: f64,
+    /// The resolved keyboard/MIDI layout: `Bindings::default()` overlaid with
+    /// `config.toml`'s `[bindings.scancodes]`/`[bindings.keys]`/`[bindings.pads]`/
+    /// `[bindings.knobs]` tables, held here so the rest of the app only ever reads
+    /// bindings through `AppConfig`.
+    pub bindings: Bindings,
+    /// Run without opening a window, rendering `headless_frame_count` frames at
+    /// `render_width`x`render_height` and exporting each through the screenshot pipeline.
+    pub headless: bool,
+    pub render_width: u32,
+    pub render_height: u32,
+    pub headless_frame_count: u32,
+    pub hud_font_path: String,
+}
+
+impl Default for AppConfig {
+    fn default() -> AppConfig {
+        AppConfig {
+            midi_device_id: None,
+            audio_host_name: None,
+            audio_device_id: None,
+            module_path: None,
+            rocket_address: None,
+            rocket_bpm: 125.0,
+            rocket_rows_per_beat: 8.0,
+            bindings: Bindings::default(),
+            headless: false,
+            render_width: 3840,
+            render_height: 2160,
+            headless_frame_count: 300,
+            hud_font_path: "assets/DejaVuSansMono.ttf".to_string(),
+        }
+    }
+}
+
+pub fn get_config() -> AppConfig {
+    let file_config: FileConfig = fs::read_to_string(CONFIG_PATH)
+        .ok()
+        .and_then(|contents| toml::from_str(&contents).ok())
+        .unwrap_or_default();
+
+    let defaults = AppConfig::default();
+
+    let mut bindings = Bindings::default();
+    bindings.apply_overrides(file_config.bindings);
+
+    AppConfig {
+        bindings,
+        midi_device_id: file_config.midi_device_id.or(defaults.midi_device_id),
+        audio_host_name: file_config.audio_host_name.or(defaults.audio_host_name),
+        audio_device_id: file_config.audio_device_id.or(defaults.audio_device_id),
+        module_path: file_config.module_path.or(defaults.module_path),
+        rocket_address: file_config.rocket_address.or(defaults.rocket_address),
+        rocket_bpm: file_config.rocket_bpm.unwrap_or(defaults.rocket_bpm),
+        rocket_rows_per_beat: file_config
+            .rocket_rows_per_beat
+            .unwrap_or(defaults.rocket_rows_per_beat),
+        headless: file_config.headless.unwrap_or(defaults.headless),
+        render_width: file_config.render_width.unwrap_or(defaults.render_width),
+        render_height: file_config.render_height.unwrap_or(defaults.render_height),
+        headless_frame_count: file_config
+            .headless_frame_count
+            .unwrap_or(defaults.headless_frame_count),
+        hud_font_path: file_config.hud_font_path.unwrap_or(defaults.hud_font_path),
+        ..defaults
+    }
+}
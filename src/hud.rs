@@ -0,0 +1,112 @@
+///
+/// On-screen HUD overlay: active preset, detected BPM, `u_time`, fullscreen/recording
+/// status, and a transient toast when a preset loads or saves. Toggleable so it stays out
+/// of the way during a performance but is available for debugging and live tweaking.
+///
+use glium::{Display, Surface};
+use glium_text_rusttype::{FontTexture, TextDisplay, TextSystem};
+use std::fs;
+use std::time::{Duration, Instant};
+
+const TOAST_DURATION: Duration = Duration::from_secs(2);
+const LINE_HEIGHT: f32 = 0.08;
+
+struct Toast {
+    message: String,
+    shown_at: Instant,
+}
+
+/// Everything the HUD needs to render a frame, gathered by `main` from wherever it lives.
+pub struct HudInfo<'a> {
+    pub preset_name: &'a str,
+    pub beat_preset_name: &'a str,
+    pub u_time: f32,
+    pub bpm: Option<f64>,
+    pub fullscreen: bool,
+    pub recording: bool,
+}
+
+pub struct Hud {
+    text_system: TextSystem,
+    // `None` when the configured font couldn't be loaded; the HUD is then silently
+    // skipped each frame rather than taking down the whole app over a cosmetic feature.
+    font: Option<FontTexture>,
+    visible: bool,
+    toast: Option<Toast>,
+}
+
+impl Hud {
+    pub fn new(display: &Display, font_path: &str) -> Hud {
+        let text_system = TextSystem::new(display);
+        let font = fs::read(font_path)
+            .ok()
+            .and_then(|bytes| FontTexture::new(display, &bytes[..], 24).ok());
+
+        if font.is_none() {
+            println!("Could not load HUD font {font_path}, HUD overlay disabled");
+        }
+
+        Hud {
+            text_system,
+            font,
+            visible: true,
+            toast: None,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    /// Show `message` for a couple of seconds, e.g. after a preset loads or saves.
+    pub fn show_toast(&mut self, message: impl Into<String>) {
+        self.toast = Some(Toast {
+            message: message.into(),
+            shown_at: Instant::now(),
+        });
+    }
+
+    pub fn draw<S: Surface>(&mut self, target: &mut S, info: &HudInfo) {
+        if let Some(toast) = &self.toast {
+            if toast.shown_at.elapsed() > TOAST_DURATION {
+                self.toast = None;
+            }
+        }
+
+        let font = match (self.visible, &self.font) {
+            (true, Some(font)) => font,
+            _ => return,
+        };
+
+        let mut lines = vec![
+            format!("Preset: {}", info.preset_name),
+            format!("Beat preset: {}", info.beat_preset_name),
+            format!("Time: {:.2}", info.u_time),
+            match info.bpm {
+                Some(bpm) => format!("BPM: {bpm:.1}"),
+                None => "BPM: --".to_string(),
+            },
+            if info.fullscreen { "Fullscreen".to_string() } else { "Windowed".to_string() },
+        ];
+
+        if info.recording {
+            lines.push("\u{25cf} REC".to_string());
+        }
+
+        if let Some(toast) = &self.toast {
+            lines.push(toast.message.clone());
+        }
+
+        for (i, line) in lines.iter().enumerate() {
+            let text = TextDisplay::new(&self.text_system, font, line);
+            let y = 0.95 - i as f32 * LINE_HEIGHT;
+            let matrix = [
+                [0.04, 0.0, 0.0, 0.0],
+                [0.0, 0.04, 0.0, 0.0],
+                [0.0, 0.0, 1.0, 0.0],
+                [-0.98, y, 0.0, 1.0],
+            ];
+            glium_text_rusttype::draw(&text, &self.text_system, target, matrix, (1.0, 1.0, 1.0, 1.0));
+        }
+    }
+}
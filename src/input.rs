@@ -0,0 +1,258 @@
+///
+/// Bindable keyboard/MIDI layout: resolves raw keyboard scancodes, `VirtualKeyCode`s and
+/// `midi::Mpd218Message`s into `action::Action`s via a user-configurable layout, so `main`
+/// dispatches actions instead of matching raw input directly.
+///
+use crate::action::Action;
+use crate::midi::Mpd218Message;
+use glium::glutin::event::VirtualKeyCode;
+use serde::Deserialize;
+use std::collections::HashMap;
+
+/// A bindable layout of keyboard, MIDI pad and MIDI knob inputs to `Action`s. Starts from
+/// `Bindings::default()` and can be overridden per-entry from `config.toml` via
+/// `apply_overrides`.
+#[derive(Debug, Clone)]
+pub struct Bindings {
+    /// Scancode bindings take priority over `keys` so number rows bind consistently
+    /// across keyboard layouts, matching the legacy scancode-based preset loading.
+    pub scancodes: HashMap<u32, Action>,
+    pub keys: HashMap<VirtualKeyCode, Action>,
+    pub pads: HashMap<u8, Action>,
+    pub knobs: HashMap<u8, Action>,
+}
+
+impl Default for Bindings {
+    fn default() -> Bindings {
+        let mut scancodes = HashMap::new();
+        for scancode in 2..=11 {
+            scancodes.insert(scancode, Action::LoadPreset((scancode - 1) % 10));
+        }
+
+        let mut keys = HashMap::new();
+        keys.insert(VirtualKeyCode::Escape, Action::Quit);
+        keys.insert(VirtualKeyCode::Return, Action::ToggleFullscreen);
+        keys.insert(VirtualKeyCode::R, Action::RandomizePreset);
+        keys.insert(VirtualKeyCode::P, Action::RegeneratePoints);
+        keys.insert(VirtualKeyCode::C, Action::ClearTextures);
+        keys.insert(VirtualKeyCode::S, Action::SavePreset);
+        keys.insert(VirtualKeyCode::Back, Action::TakeScreenshot);
+        keys.insert(VirtualKeyCode::V, Action::ToggleRecording);
+        keys.insert(VirtualKeyCode::H, Action::ToggleHud);
+
+        let mut pads = HashMap::new();
+        for pad in 0..=9 {
+            pads.insert(pad, Action::LoadPreset(pad as u32));
+        }
+        for pad in 16..=25 {
+            pads.insert(pad, Action::LoadBeatPreset((pad - 16) as u32));
+        }
+        pads.insert(10, Action::ClearTextures);
+        pads.insert(11, Action::RegeneratePoints);
+        pads.insert(12, Action::RandomizePreset);
+        pads.insert(13, Action::RandomizeBeatPreset);
+
+        let mut knobs = HashMap::new();
+        knobs.insert(0, Action::ScrubTime(0.0));
+
+        Bindings {
+            scancodes,
+            keys,
+            pads,
+            knobs,
+        }
+    }
+}
+
+/// The `[bindings]` table in `config.toml`, overlaid onto `Bindings::default()` by
+/// `Bindings::apply_overrides`.
+#[derive(Debug, Clone, Deserialize, Default)]
+#[serde(default)]
+pub struct FileBindings {
+    scancodes: HashMap<String, Action>,
+    keys: HashMap<String, Action>,
+    pads: HashMap<String, Action>,
+    knobs: HashMap<String, Action>,
+}
+
+impl Bindings {
+    pub fn resolve_key(&self, scancode: u32, virtual_keycode: Option<VirtualKeyCode>) -> Option<Action> {
+        if let Some(action) = self.scancodes.get(&scancode) {
+            return Some(*action);
+        }
+        virtual_keycode.and_then(|key| self.keys.get(&key).copied())
+    }
+
+    pub fn resolve_midi(&self, message: Mpd218Message) -> Option<Action> {
+        match message {
+            Mpd218Message::PadPressed(pad, _velocity, _) => self.pads.get(&pad).copied(),
+            Mpd218Message::KnobChanged(knob, value, _) => match self.knobs.get(&knob) {
+                Some(Action::ScrubTime(_)) => Some(Action::ScrubTime(value as f32 / 127.0)),
+                other => other.copied(),
+            },
+            _ => None,
+        }
+    }
+
+    /// Overlay `config.toml`'s `[bindings.scancodes]`/`[bindings.keys]`/`[bindings.pads]`/
+    /// `[bindings.knobs]` tables onto the defaults. Unrecognized key names or non-numeric
+    /// scancode/pad/knob ids are skipped rather than failing the whole config load.
+    pub fn apply_overrides(&mut self, file_bindings: FileBindings) {
+        for (scancode_id, action) in file_bindings.scancodes {
+            match scancode_id.parse::<u32>() {
+                Ok(scancode) => {
+                    self.scancodes.insert(scancode, action);
+                }
+                Err(_) => {
+                    eprintln!("config.toml: scancode binding {scancode_id:?} is not a valid scancode number, ignoring")
+                }
+            }
+        }
+        for (key_name, action) in file_bindings.keys {
+            match parse_virtual_keycode(&key_name) {
+                Some(key) => {
+                    self.keys.insert(key, action);
+                }
+                None => eprintln!("config.toml: unrecognized key binding name {key_name:?}, ignoring"),
+            }
+        }
+        for (pad_id, action) in file_bindings.pads {
+            match pad_id.parse::<u8>() {
+                Ok(pad) => {
+                    self.pads.insert(pad, action);
+                }
+                Err(_) => eprintln!("config.toml: pad binding {pad_id:?} is not a valid MIDI pad number, ignoring"),
+            }
+        }
+        for (knob_id, action) in file_bindings.knobs {
+            match knob_id.parse::<u8>() {
+                Ok(knob) => {
+                    self.knobs.insert(knob, action);
+                }
+                Err(_) => eprintln!("config.toml: knob binding {knob_id:?} is not a valid MIDI knob number, ignoring"),
+            }
+        }
+    }
+}
+
+/// Maps the `config.toml` key names accepted in `[bindings.keys]` to `VirtualKeyCode`s.
+/// Covers letters, digits and the named keys this app actually binds by default; extend
+/// as new keys need to be bindable.
+fn parse_virtual_keycode(name: &str) -> Option<VirtualKeyCode> {
+    use VirtualKeyCode::*;
+    Some(match name {
+        "A" => A, "B" => B, "C" => C, "D" => D, "E" => E, "F" => F, "G" => G, "H" => H,
+        "I" => I, "J" => J, "K" => K, "L" => L, "M" => M, "N" => N, "O" => O, "P" => P,
+        "Q" => Q, "R" => R, "S" => S, "T" => T, "U" => U, "V" => V, "W" => W, "X" => X,
+        "Y" => Y, "Z" => Z,
+        "Key0" | "0" => Key0, "Key1" | "1" => Key1, "Key2" | "2" => Key2,
+        "Key3" | "3" => Key3, "Key4" | "4" => Key4, "Key5" | "5" => Key5,
+        "Key6" | "6" => Key6, "Key7" | "7" => Key7, "Key8" | "8" => Key8,
+        "Key9" | "9" => Key9,
+        "Escape" => Escape,
+        "Return" | "Enter" => Return,
+        "Space" => Space,
+        "Tab" => Tab,
+        "Back" | "Backspace" => Back,
+        "Up" => Up,
+        "Down" => Down,
+        "Left" => Left,
+        "Right" => Right,
+        "F1" => F1, "F2" => F2, "F3" => F3, "F4" => F4, "F5" => F5, "F6" => F6,
+        "F7" => F7, "F8" => F8, "F9" => F9, "F10" => F10, "F11" => F11, "F12" => F12,
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file_bindings_with_scancode(id: &str, action: Action) -> FileBindings {
+        let mut scancodes = HashMap::new();
+        scancodes.insert(id.to_string(), action);
+        FileBindings {
+            scancodes,
+            ..FileBindings::default()
+        }
+    }
+
+    #[test]
+    fn resolve_key_prefers_scancode_over_a_key_override() {
+        let mut bindings = Bindings::default();
+        bindings.keys.insert(VirtualKeyCode::Key2, Action::Quit);
+        // Scancode 3 also maps to Key2 on a US layout; the scancode table should win.
+        assert_eq!(
+            bindings.resolve_key(3, Some(VirtualKeyCode::Key2)),
+            Some(Action::LoadPreset(2))
+        );
+    }
+
+    #[test]
+    fn resolve_key_falls_back_to_keys_when_scancode_is_unbound() {
+        let bindings = Bindings::default();
+        assert_eq!(bindings.resolve_key(999, Some(VirtualKeyCode::R)), Some(Action::RandomizePreset));
+    }
+
+    #[test]
+    fn apply_overrides_rebinds_a_scancode() {
+        let mut bindings = Bindings::default();
+        bindings.apply_overrides(file_bindings_with_scancode("3", Action::Quit));
+        assert_eq!(bindings.resolve_key(3, None), Some(Action::Quit));
+    }
+
+    #[test]
+    fn apply_overrides_skips_unrecognized_key_names() {
+        let mut keys = HashMap::new();
+        keys.insert("NotAKey".to_string(), Action::Quit);
+        let mut bindings = Bindings::default();
+        let before = bindings.keys.clone();
+
+        bindings.apply_overrides(FileBindings {
+            keys,
+            ..FileBindings::default()
+        });
+
+        assert_eq!(bindings.keys, before);
+    }
+
+    #[test]
+    fn apply_overrides_skips_non_numeric_pad_and_knob_ids() {
+        let mut pads = HashMap::new();
+        pads.insert("not-a-number".to_string(), Action::Quit);
+        let mut knobs = HashMap::new();
+        knobs.insert("also-not-a-number".to_string(), Action::Quit);
+        let mut bindings = Bindings::default();
+        let (before_pads, before_knobs) = (bindings.pads.clone(), bindings.knobs.clone());
+
+        bindings.apply_overrides(FileBindings {
+            pads,
+            knobs,
+            ..FileBindings::default()
+        });
+
+        assert_eq!(bindings.pads, before_pads);
+        assert_eq!(bindings.knobs, before_knobs);
+    }
+
+    #[test]
+    fn apply_overrides_rebinds_a_pad() {
+        let mut pads = HashMap::new();
+        pads.insert("10".to_string(), Action::SavePreset);
+        let mut bindings = Bindings::default();
+
+        bindings.apply_overrides(FileBindings {
+            pads,
+            ..FileBindings::default()
+        });
+
+        assert_eq!(bindings.pads.get(&10), Some(&Action::SavePreset));
+    }
+
+    #[test]
+    fn parse_virtual_keycode_recognizes_letters_and_digits_but_not_junk() {
+        assert_eq!(parse_virtual_keycode("R"), Some(VirtualKeyCode::R));
+        assert_eq!(parse_virtual_keycode("1"), Some(VirtualKeyCode::Key1));
+        assert_eq!(parse_virtual_keycode("not-a-real-key"), None);
+    }
+}
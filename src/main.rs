@@ -1,9 +1,9 @@
 ///
 /// Heavily inspired by (and code "borrowed" from): https://observablehq.com/@johnowhitaker/dotswarm-exploring-slime-mould-inspired-shaders
 ///
+use crate::action::Action as InputAction;
 use crate::preset::{Preset, PresetName};
-use chrono::Local;
-use glium::glutin::event::{ElementState, Event, StartCause, VirtualKeyCode, WindowEvent};
+use glium::glutin::event::{ElementState, Event, StartCause, WindowEvent};
 use glium::glutin::event_loop::{ControlFlow, EventLoop};
 use glium::glutin::window::Fullscreen;
 use glium::{glutin, Surface};
@@ -11,23 +11,55 @@ use std::sync::mpsc::sync_channel;
 use std::thread;
 use std::time::{Duration, Instant};
 
+pub mod action;
 pub mod beat;
 pub mod config;
+pub mod hud;
+pub mod input;
 pub mod midi;
 pub mod preset;
+pub mod rocket;
 pub mod screenshot;
 pub mod shader_pipeline;
 pub mod slime_mould;
+pub mod sound;
 
 fn main() {
     let app_config = config::get_config();
+
+    if app_config.headless {
+        run_headless(&app_config);
+        return;
+    }
+
     let midi_channel = midi::MidiChannel::new(app_config.midi_device_id);
 
     let mut beat_detector = beat::BeatDetector::new();
 
     let (beat_sender, beat_receiver) = sync_channel(64);
 
-    if app_config.audio_host_name.is_some() && app_config.audio_device_id.is_some() {
+    // A tracker module, if configured, becomes the visual clock instead of live-audio
+    // beat detection: it supplies both `u_time` and beat events on the same channel.
+    let sound_player = app_config.module_path.as_ref().and_then(|path| {
+        match sound::TrackerModule::load(path) {
+            Ok(module) => match sound::SoundPlayer::play(module, beat_sender.clone()) {
+                Ok(player) => Some(player),
+                Err(e) => {
+                    println!("Could not start playback of {path}: {e}");
+                    None
+                }
+            },
+            Err(e) => {
+                println!("Could not load tracker module {path}: {e}");
+                None
+            }
+        }
+    });
+
+    if sound_player.is_none()
+        && app_config.audio_host_name.is_some()
+        && app_config.audio_device_id.is_some()
+    {
         beat_detector.start_listening(
             app_config.audio_host_name.unwrap(),
             app_config.audio_device_id.unwrap(),
@@ -70,6 +102,9 @@ fn main() {
 
     let mut screenshot_taker = screenshot::AsyncScreenshotTaker::new(5);
 
+    let mut hud = hud::Hud::new(&display, &app_config.hud_font_path);
+    let mut last_bpm: Option<f64> = None;
+
     let mut beat_preset: preset::Preset = rand::random();
     let mut non_beat_preset = preset;
 
@@ -77,25 +112,71 @@ fn main() {
     let mut u_time_takeover = false;
     let mut beat_start_time = u_time;
     let primary_window_id = display.gl_window().window().id();
-    start_loop(event_loop, move |events| {
+
+    // If a GNU Rocket editor is reachable, let it drive u_time and preset blending
+    // instead of the ad-hoc beat-triggered transitions below.
+    let mut rocket_client = app_config.rocket_address.as_ref().and_then(|addr| {
+        match rocket::RocketClient::connect(addr) {
+            Ok(mut client) => {
+                let _ = client.get_track("blur");
+                let _ = client.get_track("speed");
+                let _ = client.get_track("hue");
+                let _ = client.get_track("preset_blend");
+                println!("Connected to GNU Rocket editor at {addr}");
+                Some(client)
+            }
+            Err(e) => {
+                println!("Could not connect to GNU Rocket editor at {addr}: {e}");
+                None
+            }
+        }
+    });
+    let mut start_time = Instant::now();
+    let mut rocket_transition_started = false;
+    let mut rocket_paused_at: Option<Instant> = None;
+    start_loop(event_loop, move |events, fixed_steps| {
         screenshot_taker.next_frame();
 
         let mut got_beat = false;
-        for _bpm in beat_receiver.try_iter() {
+        for bpm in beat_receiver.try_iter() {
             got_beat = true;
+            last_bpm = Some(bpm);
             //println!("Got beat! BPM: {bpm:.2}");
         }
 
+        // Step the simulation at a fixed rate, independent of the display's refresh rate,
+        // so the same preset looks identical on a 60 Hz and a 144 Hz monitor.
+        for _ in 0..fixed_steps {
+            if sound_player.is_none() && !u_time_takeover {
+                u_time += FIXED_UPDATE_DT;
+            }
+            slime_mould.update();
+        }
+
+        // Rocket, when connected, drives u_time itself later this frame; don't let the
+        // tracker clock fight it for control of the same variable.
+        if rocket_client.is_none() {
+            if let Some(player) = sound_player.as_ref() {
+                u_time = player.time_seconds() as f32;
+            }
+        }
+
         let mut target = display.draw();
         target.clear_color(0.0, 0.0, 0.0, 1.0);
-        slime_mould.draw(&mut target, &display, u_time);
+        draw_slime_mould(&mut slime_mould, &mut target, &display, u_time);
+        hud.draw(
+            &mut target,
+            &hud::HudInfo {
+                preset_name: &format!("{:?}", slime_mould.get_preset().name),
+                beat_preset_name: &format!("{:?}", beat_preset.name),
+                u_time,
+                bpm: last_bpm,
+                fullscreen,
+                recording: screenshot_taker.is_recording(),
+            },
+        );
         target.finish().unwrap();
 
-        if !u_time_takeover {
-            u_time += 0.02;
-        }
-        slime_mould.update();
-
         let mut action = Action::Continue;
 
         let mut randomize_beat_preset = false;
@@ -106,10 +187,13 @@ fn main() {
         let mut backspace_pressed = false;
         let mut clear_textures = false;
         let mut save_preset = false;
+        let mut toggle_recording = false;
 
         let mut load_preset_number = -1;
         let mut load_beat_preset_number = -1;
 
+        let mut input_actions = Vec::new();
+
         for event in events {
             if let Event::WindowEvent { event, window_id } = event {
                 if *window_id == primary_window_id {
@@ -117,19 +201,11 @@ fn main() {
                         WindowEvent::CloseRequested => action = Action::Stop,
                         WindowEvent::KeyboardInput { input, .. } => {
                             if let ElementState::Pressed = input.state {
-                                match input.virtual_keycode {
-                                    Some(VirtualKeyCode::Escape) => stop_event_loop = true,
-                                    Some(VirtualKeyCode::Return) => toggle_fullscreen = true,
-                                    Some(VirtualKeyCode::R) => randomize_preset = true,
-                                    Some(VirtualKeyCode::P) => regenerate_points = true,
-                                    Some(VirtualKeyCode::C) => clear_textures = true,
-                                    Some(VirtualKeyCode::S) => save_preset = true,
-                                    Some(VirtualKeyCode::Back) => backspace_pressed = true,
-                                    _ => (),
-                                }
-                                // If we received a number
-                                if input.scancode >= 2 && input.scancode <= 11 {
-                                    load_preset_number = ((input.scancode - 1) % 10) as i32;
+                                if let Some(resolved) = app_config
+                                    .bindings
+                                    .resolve_key(input.scancode, input.virtual_keycode)
+                                {
+                                    input_actions.push(resolved);
                                 }
                             }
                         }
@@ -142,30 +218,29 @@ fn main() {
         // Midi receiver
         for m in midi_channel.try_iter() {
             println!("{m:?}");
-            match m {
-                midi::Mpd218Message::PadPressed(pad, _velocity, _) => {
-                    if pad <= 9 {
-                        load_preset_number = pad as i32;
-                    } else if pad >= 16 && pad <= 25 {
-                        load_beat_preset_number = (pad - 16) as i32;
-                    } else {
-                        match pad {
-                            10 => clear_textures = true,
-                            11 => regenerate_points = true,
-                            12 => randomize_preset = true,
-                            13 => randomize_beat_preset = true,
-                            _ => (),
-                        }
-                    }
-                }
-                midi::Mpd218Message::KnobChanged(knob, value, _) => {
-                    if knob == 0 {
-                        u_time = value as f32 / 127.0;
-                        //println!("value: {value} u_time: {u_time}");
-                        u_time_takeover = true;
-                    }
+            if let Some(resolved) = app_config.bindings.resolve_midi(m) {
+                input_actions.push(resolved);
+            }
+        }
+
+        for resolved in input_actions {
+            match resolved {
+                InputAction::Quit => stop_event_loop = true,
+                InputAction::ToggleFullscreen => toggle_fullscreen = true,
+                InputAction::RandomizePreset => randomize_preset = true,
+                InputAction::RandomizeBeatPreset => randomize_beat_preset = true,
+                InputAction::RegeneratePoints => regenerate_points = true,
+                InputAction::ClearTextures => clear_textures = true,
+                InputAction::SavePreset => save_preset = true,
+                InputAction::TakeScreenshot => backspace_pressed = true,
+                InputAction::ToggleRecording => toggle_recording = true,
+                InputAction::ToggleHud => hud.toggle(),
+                InputAction::LoadPreset(n) => load_preset_number = n as i32,
+                InputAction::LoadBeatPreset(n) => load_beat_preset_number = n as i32,
+                InputAction::ScrubTime(axis) => {
+                    u_time = axis;
+                    u_time_takeover = true;
                 }
-                _ => (),
             }
         }
 
@@ -196,45 +271,105 @@ fn main() {
 
         if load_preset_number >= 0 {
             // Load presets
-            slime_mould.transition_preset(
-                slime_mould.get_preset(),
-                Preset::new(PresetName::from_u32(load_preset_number as u32)),
-                u_time,
-                1.0,
-            );
+            let next_preset = Preset::new(PresetName::from_u32(load_preset_number as u32));
+            slime_mould.transition_preset(slime_mould.get_preset(), next_preset, u_time, 1.0);
             slime_mould.reset_points();
             u_time_takeover = false;
+            hud.show_toast(format!("Loaded {next_preset:?}"));
         }
 
         if save_preset {
             slime_mould.save_preset();
+            hud.show_toast("Preset saved");
         }
 
-        // /*
-        if got_beat {
+        if let Some(client) = rocket_client.as_mut() {
+            if let Err(e) = client.poll() {
+                println!("Lost connection to GNU Rocket editor: {e}");
+                rocket_client = None;
+                rocket_transition_started = false;
+                rocket_paused_at = None;
+                u_time_takeover = false;
+            }
+        }
+
+        if let Some(client) = rocket_client.as_mut() {
+            // A SET_ROW from the editor (e.g. scrubbing the timeline) is a one-shot seek:
+            // rebase the clock so the time-derived row continues from the new position
+            // instead of freezing there forever.
+            if let Some(seek_row) = client.take_seek() {
+                let seconds =
+                    seek_row / (app_config.rocket_bpm / 60.0 * app_config.rocket_rows_per_beat);
+                start_time = Instant::now() - Duration::from_secs_f64(seconds.max(0.0));
+            }
+
+            // While paused, freeze the clock rather than let elapsed() keep advancing, so
+            // unpausing resumes from where it left off instead of jumping ahead by however
+            // long the pause lasted.
+            if client.paused {
+                rocket_paused_at.get_or_insert_with(Instant::now);
+            } else if let Some(paused_at) = rocket_paused_at.take() {
+                start_time += paused_at.elapsed();
+            }
+
+            let clock_now = rocket_paused_at.unwrap_or_else(Instant::now);
+            let row = rocket::RocketClient::row_from_time(
+                clock_now.duration_since(start_time).as_secs_f64(),
+                app_config.rocket_bpm,
+                app_config.rocket_rows_per_beat,
+            );
+
+            let blur_track = client.get_track("blur").unwrap();
+            let speed_track = client.get_track("speed").unwrap();
+            let hue_track = client.get_track("hue").unwrap();
+            let preset_blend_track = client.get_track("preset_blend").unwrap();
+
+            // SlimeMould's expected parameter ranges: blur and hue are normalized
+            // fractions of their effect, speed is a multiplier on the base simulation
+            // rate. Clamp so an out-of-range or garbage value typed into the editor can't
+            // push the simulation into a degenerate state.
+            let blur = client.value_at_row(blur_track, row).clamp(0.0, 1.0);
+            let speed = client.value_at_row(speed_track, row).clamp(0.0, 4.0);
+            let hue = client.value_at_row(hue_track, row).clamp(0.0, 1.0);
+            let blend = client.value_at_row(preset_blend_track, row).clamp(0.0, 1.0);
+
+            u_time_takeover = true;
+            u_time = row as f32;
+
+            slime_mould.set_rocket_params(blur, speed, hue);
+
+            // Kick off the non_beat/beat transition once; after that the editor drives the
+            // blend weight directly every frame instead of re-triggering a timed
+            // transition (whose 4th argument elsewhere in this file is a duration, not a
+            // weight).
+            if !rocket_transition_started {
+                slime_mould.transition_preset(non_beat_preset, beat_preset, u_time, 1.0);
+                rocket_transition_started = true;
+            }
+            slime_mould.set_preset_blend(blend);
+        } else if got_beat {
+            // /*
             beat_start_time = u_time;
             non_beat_preset = slime_mould.get_preset();
             slime_mould.transition_preset(non_beat_preset, beat_preset, u_time, 0.2);
-        } else {
-            if beat_start_time > 0.0 {
-                if (u_time - beat_start_time) > 0.2 {
-                    slime_mould.transition_preset(beat_preset, non_beat_preset, u_time, 0.1);
-                    beat_start_time = -1.0;
-                }
-            }
+        } else if beat_start_time > 0.0 && (u_time - beat_start_time) > 0.2 {
+            slime_mould.transition_preset(beat_preset, non_beat_preset, u_time, 0.1);
+            beat_start_time = -1.0;
         } // */
         if backspace_pressed {
             println!("Taking screenshot...");
             screenshot_taker.take_screenshot(&display);
         }
 
-        for image_data in screenshot_taker.pickup_screenshots() {
-            let image_name = format!(
-                "slime_mould-{}.png",
-                Local::now().format("%Y-%m-%d_%H%M%S%.f")
-            );
+        if toggle_recording {
+            screenshot_taker.toggle_recording();
+        }
+
+        screenshot_taker.record_frame(&display);
+
+        for (image_data, path) in screenshot_taker.pickup_screenshots() {
             thread::spawn(move || {
-                screenshot::save_screenshot(image_data, image_name);
+                screenshot::save_screenshot(image_data, path);
             });
         }
 
@@ -264,17 +399,87 @@ fn main() {
     });
 }
 
+/// Draws one frame through `SlimeMould::draw`. Generic over `glium::Surface` so this one
+/// function serves both the on-screen path (`glium::Frame`) and the offscreen path used by
+/// `run_headless` (`SimpleFrameBuffer`) — the two share no common surface type otherwise.
+/// Routing both call sites through this generic helper keeps that requirement a compile
+/// error at the call site, should `draw` ever be narrowed to a concrete surface type.
+fn draw_slime_mould<S: Surface>(
+    slime_mould: &mut slime_mould::SlimeMould,
+    target: &mut S,
+    display: &glium::Display,
+    u_time: f32,
+) {
+    slime_mould.draw(target, display, u_time);
+}
+
+/// Render `app_config.headless_frame_count` frames at `render_width`x`render_height`
+/// without opening a visible window, exporting each through the same screenshot pipeline
+/// used for on-screen captures. Useful for poster-resolution stills or an offline frame
+/// sequence that's larger than the display it was produced on.
+fn run_headless(app_config: &config::AppConfig) {
+    let (width, height) = (app_config.render_width, app_config.render_height);
+
+    let event_loop = glutin::event_loop::EventLoop::new();
+    let wb = glutin::window::WindowBuilder::new()
+        .with_inner_size(glutin::dpi::PhysicalSize::new(width, height))
+        .with_visible(false);
+    let cb = glutin::ContextBuilder::new().with_depth_buffer(24);
+    let display = glium::Display::new(wb, cb, &event_loop).unwrap();
+
+    let preset = rand::random();
+    let mut slime_mould = slime_mould::SlimeMould::new(&display, width, height, preset);
+
+    let render_target = glium::texture::Texture2d::empty(&display, width, height).unwrap();
+    let mut framebuffer =
+        glium::framebuffer::SimpleFrameBuffer::new(&display, &render_target).unwrap();
+
+    let mut u_time: f32 = 0.0;
+    for frame in 0..app_config.headless_frame_count {
+        framebuffer.clear_color(0.0, 0.0, 0.0, 1.0);
+        draw_slime_mould(&mut slime_mould, &mut framebuffer, &display, u_time);
+
+        slime_mould.update();
+        u_time += FIXED_UPDATE_DT;
+
+        let image: glium::texture::RawImage2d<u8> = render_target.read();
+        let path = std::path::PathBuf::from(format!("slime_mould-{frame:06}.png"));
+        screenshot::save_screenshot(image, path);
+    }
+
+    println!("Wrote {} frames at {width}x{height}", app_config.headless_frame_count);
+}
+
 pub enum Action {
     Stop,
     Continue,
 }
 
+/// Simulation update rate, independent of the display's refresh rate.
+const FIXED_UPDATE_HZ: f64 = 120.0;
+const FIXED_UPDATE_DT: f32 = (1.0 / FIXED_UPDATE_HZ) as f32;
+const FIXED_UPDATE_DT_NANOS: u64 = (1_000_000_000.0 / FIXED_UPDATE_HZ) as u64;
+/// Upper bound on fixed-timestep catch-up per frame. Without this, a long stall (e.g. the
+/// window being dragged or the process being suspended) would leave a huge backlog in
+/// `accumulator` and cause a burst of simulation steps large enough to stall the next
+/// several frames trying to catch up (a "spiral of death"). Excess accumulated time is
+/// dropped instead, so the simulation slows down during a stall rather than compounding it.
+const MAX_FIXED_STEPS_PER_FRAME: u32 = 8;
+
+/// Drives `callback` on a timer about every 8.3 ms (half of a 60 Hz frame), whatever the
+/// display's actual refresh rate, passing the number of fixed-timestep simulation steps
+/// that have accumulated since the previous call so the caller can advance the
+/// simulation at a constant rate regardless of how fast frames arrive.
 pub fn start_loop<F>(event_loop: EventLoop<()>, mut callback: F)
 where
-    F: 'static + FnMut(&Vec<Event<'_, ()>>) -> Action,
+    F: 'static + FnMut(&Vec<Event<'_, ()>>, u32) -> Action,
 {
     let mut events_buffer = Vec::new();
     let mut next_frame_time = Instant::now();
+    let mut last_update = Instant::now();
+    let mut accumulator = Duration::ZERO;
+    let fixed_dt = Duration::from_nanos(FIXED_UPDATE_DT_NANOS);
+
     event_loop.run(move |event, _, control_flow| {
         let run_callback = match event.to_static() {
             Some(Event::NewEvents(cause)) => matches!(
@@ -292,9 +497,24 @@ where
         };
 
         let action = if run_callback {
-            let action = callback(&events_buffer);
+            let now = Instant::now();
+            accumulator += now.duration_since(last_update);
+            last_update = now;
+
+            let mut fixed_steps = 0;
+            while accumulator >= fixed_dt && fixed_steps < MAX_FIXED_STEPS_PER_FRAME {
+                accumulator -= fixed_dt;
+                fixed_steps += 1;
+            }
+            if accumulator >= fixed_dt {
+                // Still behind after the cap: drop the backlog rather than let it grow
+                // forever, so a single long stall doesn't cause every subsequent frame to
+                // max out fixed_steps trying to catch up.
+                accumulator = Duration::ZERO;
+            }
+
+            let action = callback(&events_buffer, fixed_steps);
             next_frame_time = Instant::now() + Duration::from_nanos(16666667) / 2;
-            // TODO: Add back the old accumulator loop in some way
 
             events_buffer.clear();
             action
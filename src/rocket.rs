@@ -0,0 +1,322 @@
+///
+/// Client for the GNU Rocket sync-tracker editor protocol: https://github.com/rocket/rocket
+///
+/// Connects to a running Rocket editor over TCP, requests named tracks, and evaluates
+/// them against the current row so presets and shader parameters can be driven from an
+/// authored timeline instead of ad-hoc beat detection.
+///
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::net::TcpStream;
+
+const GREETING_CLIENT: &[u8] = b"hello, synctracker!";
+const GREETING_SERVER: &[u8] = b"hello, demo!";
+
+const SET_KEY: u8 = 0;
+const DELETE_KEY: u8 = 1;
+const GET_TRACK: u8 = 2;
+const SET_ROW: u8 = 3;
+const PAUSE: u8 = 4;
+const SAVE_TRACKS: u8 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    Step,
+    Linear,
+    Smooth,
+    Ramp,
+}
+
+impl Interpolation {
+    fn from_u8(byte: u8) -> Interpolation {
+        match byte {
+            0 => Interpolation::Step,
+            1 => Interpolation::Linear,
+            2 => Interpolation::Smooth,
+            3 => Interpolation::Ramp,
+            _ => Interpolation::Step,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Key {
+    pub row: u32,
+    pub value: f32,
+    pub interpolation: Interpolation,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Track {
+    pub name: String,
+    keys: Vec<Key>,
+}
+
+impl Track {
+    fn set_key(&mut self, key: Key) {
+        match self.keys.binary_search_by_key(&key.row, |k| k.row) {
+            Ok(i) => self.keys[i] = key,
+            Err(i) => self.keys.insert(i, key),
+        }
+    }
+
+    fn delete_key(&mut self, row: u32) {
+        if let Ok(i) = self.keys.binary_search_by_key(&row, |k| k.row) {
+            self.keys.remove(i);
+        }
+    }
+
+    /// Evaluate the track's value at a (possibly fractional) row.
+    pub fn value_at_row(&self, row: f64) -> f32 {
+        if self.keys.is_empty() {
+            return 0.0;
+        }
+
+        let first = self.keys.first().unwrap();
+        if row <= first.row as f64 {
+            return first.value;
+        }
+
+        let last = self.keys.last().unwrap();
+        if row >= last.row as f64 {
+            return last.value;
+        }
+
+        let next_index = self
+            .keys
+            .iter()
+            .position(|k| k.row as f64 > row)
+            .unwrap();
+        let k1 = &self.keys[next_index - 1];
+        let k2 = &self.keys[next_index];
+
+        let mut t = (row - k1.row as f64) / (k2.row as f64 - k1.row as f64);
+        match k1.interpolation {
+            Interpolation::Step => return k1.value,
+            Interpolation::Linear => (),
+            Interpolation::Smooth => t = t * t * (3.0 - 2.0 * t),
+            Interpolation::Ramp => t = t * t,
+        }
+
+        (k1.value as f64 + (k2.value - k1.value) as f64 * t) as f32
+    }
+}
+
+/// A connection to a running GNU Rocket editor.
+pub struct RocketClient {
+    stream: TcpStream,
+    tracks: Vec<Track>,
+    track_indices: HashMap<String, usize>,
+    pub paused: bool,
+    /// A row requested by the editor via `SET_ROW` (e.g. scrubbing the timeline) that the
+    /// caller hasn't consumed yet. One-shot: `take_seek` clears it so a seek rebases the
+    /// caller's clock instead of pinning the row forever.
+    seek_row: Option<f64>,
+    /// Bytes read but not yet enough to form a whole command, e.g. when the editor's
+    /// write lands in more than one TCP segment.
+    read_buf: Vec<u8>,
+}
+
+impl RocketClient {
+    pub fn connect(addr: &str) -> io::Result<RocketClient> {
+        let mut stream = TcpStream::connect(addr)?;
+        stream.write_all(GREETING_CLIENT)?;
+
+        let mut reply = [0u8; GREETING_SERVER.len()];
+        stream.read_exact(&mut reply)?;
+        if reply != GREETING_SERVER {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "unexpected greeting from rocket editor",
+            ));
+        }
+
+        stream.set_nonblocking(true)?;
+
+        Ok(RocketClient {
+            stream,
+            tracks: Vec::new(),
+            track_indices: HashMap::new(),
+            paused: false,
+            seek_row: None,
+            read_buf: Vec::new(),
+        })
+    }
+
+    /// Take the editor's most recent `SET_ROW` seek, if one arrived since the last call.
+    /// Returns `None` once the seek has been consumed, so the caller sees each scrub
+    /// request exactly once instead of it sticking around as a frozen row.
+    pub fn take_seek(&mut self) -> Option<f64> {
+        self.seek_row.take()
+    }
+
+    /// Look up a track by name, requesting it from the editor the first time it's seen.
+    pub fn get_track(&mut self, name: &str) -> io::Result<usize> {
+        if let Some(&index) = self.track_indices.get(name) {
+            return Ok(index);
+        }
+
+        let index = self.tracks.len();
+        self.tracks.push(Track {
+            name: name.to_string(),
+            keys: Vec::new(),
+        });
+        self.track_indices.insert(name.to_string(), index);
+
+        let mut packet = vec![GET_TRACK];
+        packet.extend_from_slice(&(name.len() as u32).to_be_bytes());
+        packet.extend_from_slice(name.as_bytes());
+        self.stream.write_all(&packet)?;
+
+        Ok(index)
+    }
+
+    pub fn value_at_row(&self, track_index: usize, row: f64) -> f32 {
+        self.tracks
+            .get(track_index)
+            .map(|track| track.value_at_row(row))
+            .unwrap_or(0.0)
+    }
+
+    /// Convert an elapsed time in seconds to a (fractional) row, given the song's tempo.
+    pub fn row_from_time(seconds: f64, bpm: f64, rows_per_beat: f64) -> f64 {
+        seconds * (bpm / 60.0) * rows_per_beat
+    }
+
+    /// Drain and apply any pending commands from the editor. Non-blocking: reads
+    /// whatever bytes are currently available into an internal buffer and dispatches as
+    /// many complete commands as that buffer holds, leaving a trailing partial command
+    /// (split across TCP segments) for the next call instead of erroring on it.
+    pub fn poll(&mut self) -> io::Result<()> {
+        let mut chunk = [0u8; 4096];
+        loop {
+            match self.stream.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => self.read_buf.extend_from_slice(&chunk[..n]),
+                Err(e) if e.kind() == io::ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        self.dispatch_buffered_commands();
+        Ok(())
+    }
+
+    fn dispatch_buffered_commands(&mut self) {
+        while let Some(&opcode) = self.read_buf.first() {
+            let payload_len = match opcode {
+                SET_KEY => 13,
+                DELETE_KEY => 8,
+                SET_ROW => 4,
+                PAUSE => 1,
+                SAVE_TRACKS => 0,
+                _ => {
+                    // Unrecognized opcode: drop it rather than stalling forever on it.
+                    self.read_buf.remove(0);
+                    continue;
+                }
+            };
+
+            if self.read_buf.len() < 1 + payload_len {
+                // The rest of this command hasn't arrived yet; wait for the next poll.
+                break;
+            }
+
+            let payload: Vec<u8> = self.read_buf.drain(..1 + payload_len).skip(1).collect();
+            self.apply_command(opcode, &payload);
+        }
+    }
+
+    fn apply_command(&mut self, opcode: u8, payload: &[u8]) {
+        match opcode {
+            SET_KEY => {
+                let track = u32::from_be_bytes(payload[0..4].try_into().unwrap());
+                let row = u32::from_be_bytes(payload[4..8].try_into().unwrap());
+                let value = f32::from_bits(u32::from_be_bytes(payload[8..12].try_into().unwrap()));
+                let interpolation = Interpolation::from_u8(payload[12]);
+                if let Some(track) = self.tracks.get_mut(track as usize) {
+                    track.set_key(Key {
+                        row,
+                        value,
+                        interpolation,
+                    });
+                }
+            }
+            DELETE_KEY => {
+                let track = u32::from_be_bytes(payload[0..4].try_into().unwrap());
+                let row = u32::from_be_bytes(payload[4..8].try_into().unwrap());
+                if let Some(track) = self.tracks.get_mut(track as usize) {
+                    track.delete_key(row);
+                }
+            }
+            SET_ROW => {
+                let row = u32::from_be_bytes(payload[0..4].try_into().unwrap());
+                self.seek_row = Some(row as f64);
+            }
+            PAUSE => self.paused = payload[0] != 0,
+            SAVE_TRACKS => (),
+            _ => unreachable!("filtered out by dispatch_buffered_commands"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track_with(keys: &[(u32, f32, Interpolation)]) -> Track {
+        let mut track = Track::default();
+        for &(row, value, interpolation) in keys {
+            track.set_key(Key {
+                row,
+                value,
+                interpolation,
+            });
+        }
+        track
+    }
+
+    #[test]
+    fn value_at_row_clamps_before_first_and_after_last_key() {
+        let track = track_with(&[(4, 1.0, Interpolation::Linear), (8, 2.0, Interpolation::Linear)]);
+        assert_eq!(track.value_at_row(0.0), 1.0);
+        assert_eq!(track.value_at_row(100.0), 2.0);
+    }
+
+    #[test]
+    fn value_at_row_step_holds_the_left_key() {
+        let track = track_with(&[(0, 1.0, Interpolation::Step), (4, 3.0, Interpolation::Step)]);
+        assert_eq!(track.value_at_row(3.9), 1.0);
+    }
+
+    #[test]
+    fn value_at_row_linear_interpolates_evenly() {
+        let track = track_with(&[(0, 0.0, Interpolation::Linear), (4, 4.0, Interpolation::Linear)]);
+        assert_eq!(track.value_at_row(1.0), 1.0);
+        assert_eq!(track.value_at_row(2.0), 2.0);
+    }
+
+    #[test]
+    fn value_at_row_smooth_eases_at_the_endpoints() {
+        let track = track_with(&[(0, 0.0, Interpolation::Smooth), (4, 4.0, Interpolation::Smooth)]);
+        // Smoothstep's derivative is zero at t=0 and t=1, so it starts out under the
+        // value that linear interpolation would give at the same row.
+        assert!(track.value_at_row(1.0) < 1.0);
+        assert_eq!(track.value_at_row(2.0), 2.0);
+    }
+
+    #[test]
+    fn value_at_row_ramp_accelerates_into_the_right_key() {
+        let track = track_with(&[(0, 0.0, Interpolation::Ramp), (4, 4.0, Interpolation::Ramp)]);
+        // t^2 stays below the linear midpoint before the target row.
+        assert!(track.value_at_row(2.0) < 2.0);
+        assert_eq!(track.value_at_row(4.0), 4.0);
+    }
+
+    #[test]
+    fn row_from_time_scales_by_bpm_and_rows_per_beat() {
+        // 120 BPM is 2 beats/sec; at 8 rows/beat that's 16 rows/sec.
+        let row = RocketClient::row_from_time(2.0, 120.0, 8.0);
+        assert_eq!(row, 32.0);
+    }
+}
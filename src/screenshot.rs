@@ -0,0 +1,150 @@
+///
+/// Non-stalling screenshot capture: GPU pixel-buffer readbacks are queued on the frame
+/// they're requested and picked up a few frames later once the driver has finished the
+/// copy, so saving a frame to disk never blocks the render loop. A record mode queues
+/// every rendered frame this way and writes a numbered PNG sequence for one session.
+///
+use chrono::Local;
+use glium::texture::pixel_buffer::PixelBuffer;
+use glium::texture::RawImage2d;
+use glium::{Display, Surface};
+use image::{ImageBuffer, Rgba};
+use std::path::PathBuf;
+
+struct PendingRead {
+    buffer: PixelBuffer<(u8, u8, u8, u8)>,
+    size: (u32, u32),
+    path: PathBuf,
+    frames_until_ready: u32,
+}
+
+pub struct AsyncScreenshotTaker {
+    delay_frames: u32,
+    pending: Vec<PendingRead>,
+    recording: bool,
+    record_dir: Option<PathBuf>,
+    recorded_frame_count: u32,
+}
+
+impl AsyncScreenshotTaker {
+    /// `delay_frames` is how many frames to let the GPU work through before the pixel
+    /// buffer backing a queued screenshot is read back, so the read doesn't stall on a
+    /// copy that's still in flight.
+    pub fn new(delay_frames: u32) -> AsyncScreenshotTaker {
+        AsyncScreenshotTaker {
+            delay_frames,
+            pending: Vec::new(),
+            recording: false,
+            record_dir: None,
+            recorded_frame_count: 0,
+        }
+    }
+
+    /// Ages the pending queue; call once per frame before drawing.
+    pub fn next_frame(&mut self) {
+        for read in &mut self.pending {
+            read.frames_until_ready = read.frames_until_ready.saturating_sub(1);
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// Start or stop continuous frame-sequence capture. Starting creates a fresh
+    /// timestamped output directory; stopping prints how many frames were captured.
+    pub fn toggle_recording(&mut self) {
+        if self.recording {
+            self.recording = false;
+            println!(
+                "Stopped recording ({} frames captured)",
+                self.recorded_frame_count
+            );
+            self.recorded_frame_count = 0;
+        } else {
+            let dir = PathBuf::from(format!(
+                "recording-{}",
+                Local::now().format("%Y-%m-%d_%H%M%S")
+            ));
+            std::fs::create_dir_all(&dir).unwrap();
+            println!("Recording to {}", dir.display());
+            self.record_dir = Some(dir);
+            self.recording = true;
+        }
+    }
+
+    /// Queue an async readback of the display's current front buffer under `path`.
+    fn queue(&mut self, display: &Display, path: PathBuf) {
+        let (width, height) = display.get_framebuffer_dimensions();
+        let buffer: PixelBuffer<(u8, u8, u8, u8)> = display.read_front_buffer().unwrap();
+        self.pending.push(PendingRead {
+            buffer,
+            size: (width, height),
+            path,
+            frames_until_ready: self.delay_frames,
+        });
+    }
+
+    /// Queue a single, one-off screenshot (e.g. the Backspace-bound action).
+    pub fn take_screenshot(&mut self, display: &Display) {
+        let path = PathBuf::from(format!(
+            "slime_mould-{}.png",
+            Local::now().format("%Y-%m-%d_%H%M%S%.f")
+        ));
+        self.queue(display, path);
+    }
+
+    /// While recording, queue the frame that was just rendered into the session's
+    /// numbered sequence. No-op when not recording.
+    pub fn record_frame(&mut self, display: &Display) {
+        if !self.recording {
+            return;
+        }
+
+        self.recorded_frame_count += 1;
+        let dir = self.record_dir.clone().unwrap_or_default();
+        let path = dir.join(format!("slime_mould-{:06}.png", self.recorded_frame_count));
+        self.queue(display, path);
+    }
+
+    /// Drain any queued captures whose GPU readback has had time to complete.
+    pub fn pickup_screenshots(&mut self) -> Vec<(RawImage2d<'static, u8>, PathBuf)> {
+        let mut ready = Vec::new();
+
+        let (done, still_pending): (Vec<_>, Vec<_>) = std::mem::take(&mut self.pending)
+            .into_iter()
+            .partition(|read| read.frames_until_ready == 0);
+        self.pending = still_pending;
+
+        for read in done {
+            let pixels: Vec<(u8, u8, u8, u8)> = read.buffer.read().unwrap();
+            let data: Vec<u8> = pixels
+                .into_iter()
+                .flat_map(|(r, g, b, a)| [r, g, b, a])
+                .collect();
+            let image = RawImage2d::from_raw_rgba(data, read.size);
+            ready.push((image, read.path));
+        }
+
+        ready
+    }
+}
+
+/// Write a captured frame to disk as a PNG, flipping it right-side up first since GL's
+/// framebuffer origin is bottom-left.
+pub fn save_screenshot(image: RawImage2d<u8>, path: PathBuf) {
+    let width = image.width;
+    let height = image.height;
+    let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, image.data.into_owned())
+        .expect("pixel buffer did not match its reported dimensions");
+
+    if let Some(parent) = path.parent() {
+        if !parent.as_os_str().is_empty() {
+            std::fs::create_dir_all(parent).unwrap();
+        }
+    }
+
+    image::imageops::flip_vertical(&buffer)
+        .save(&path)
+        .unwrap_or_else(|e| println!("Failed to save screenshot {}: {e}", path.display()));
+}
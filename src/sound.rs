@@ -0,0 +1,480 @@
+///
+/// Tracker-module (XM) playback used to drive the visuals from a reproducible clock
+/// instead of live-audio beat detection. Parses the XM header/pattern data well enough to
+/// derive real tempo and song structure, and synthesizes a tone per active note from that
+/// data (full sample-based instrument playback/envelopes are out of scope here).
+///
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::mpsc::SyncSender;
+use std::sync::{Arc, Mutex};
+
+const XM_MAGIC: &[u8] = b"Extended Module: ";
+/// Pitch of XM note 1 (C-0), taken as scientific-pitch C0.
+const C0_FREQUENCY_HZ: f32 = 16.3516;
+
+#[derive(Debug)]
+pub enum SoundError {
+    NoOutputDevice,
+    Config(cpal::DefaultStreamConfigError),
+    Build(cpal::BuildStreamError),
+    Play(cpal::PlayStreamError),
+}
+
+impl fmt::Display for SoundError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SoundError::NoOutputDevice => write!(f, "no default audio output device"),
+            SoundError::Config(e) => write!(f, "no usable output config: {e}"),
+            SoundError::Build(e) => write!(f, "could not build output stream: {e}"),
+            SoundError::Play(e) => write!(f, "could not start output stream: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SoundError {}
+
+/// A snapshot of where playback currently is within the song.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SongPosition {
+    pub order: u32,
+    pub pattern: u32,
+    pub row: u32,
+    pub tempo: u32,
+    pub bpm: u32,
+    /// Seconds elapsed since playback started, used to drive `u_time` deterministically.
+    pub elapsed_seconds: f64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct NoteEvent {
+    /// 1-96 is a playable note, 97 is a note-off, 0 means "no note in this slot".
+    note: u8,
+    volume: u8,
+}
+
+struct Pattern {
+    rows: Vec<Vec<NoteEvent>>,
+}
+
+/// A parsed, playable XM module: header fields plus per-row note events, enough to derive
+/// real tempo/song-structure timing without decoding actual instrument samples.
+pub struct TrackerModule {
+    channels: usize,
+    default_tempo: u32,
+    default_bpm: u32,
+    song_length: usize,
+    restart_position: usize,
+    order_table: Vec<u8>,
+    patterns: Vec<Pattern>,
+}
+
+impl TrackerModule {
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<TrackerModule> {
+        let bytes = fs::read(path)?;
+        TrackerModule::parse(&bytes)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a recognized XM file"))
+    }
+
+    fn parse(bytes: &[u8]) -> Option<TrackerModule> {
+        if bytes.len() < 60 + 20 || &bytes[0..XM_MAGIC.len()] != XM_MAGIC {
+            return None;
+        }
+
+        let header_size = read_u32(bytes, 60)? as usize;
+        let song_length = read_u16(bytes, 64)? as usize;
+        let restart_position = read_u16(bytes, 66)? as usize;
+        let channels = read_u16(bytes, 68)? as usize;
+        let num_patterns = read_u16(bytes, 70)? as usize;
+        let default_tempo = read_u16(bytes, 76)? as u32;
+        let default_bpm = read_u16(bytes, 78)? as u32;
+
+        let order_table_start = 80;
+        let order_table = bytes
+            .get(order_table_start..order_table_start + song_length.min(256))?
+            .to_vec();
+
+        let mut offset = 60 + header_size;
+        let mut patterns = Vec::with_capacity(num_patterns);
+        for _ in 0..num_patterns {
+            let pattern_header_len = read_u32(bytes, offset)? as usize;
+            let num_rows = read_u16(bytes, offset + 5)? as usize;
+            let packed_size = read_u16(bytes, offset + 7)? as usize;
+            let data_start = offset + pattern_header_len;
+            let data = bytes.get(data_start..data_start + packed_size)?;
+
+            patterns.push(parse_pattern(data, num_rows, channels));
+            offset = data_start + packed_size;
+        }
+
+        Some(TrackerModule {
+            channels,
+            default_tempo: default_tempo.max(1),
+            default_bpm: default_bpm.max(1),
+            song_length,
+            restart_position,
+            order_table,
+            patterns,
+        })
+    }
+}
+
+fn read_u16(bytes: &[u8], offset: usize) -> Option<u16> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|s| u16::from_le_bytes([s[0], s[1]]))
+}
+
+fn read_u32(bytes: &[u8], offset: usize) -> Option<u32> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|s| u32::from_le_bytes([s[0], s[1], s[2], s[3]]))
+}
+
+/// Unpack one pattern's note data. Each channel slot is either a single "compression"
+/// byte naming which of (note, instrument, volume, effect, param) follow, or (in the
+/// legacy uncompressed form) all five fields unconditionally.
+fn parse_pattern(data: &[u8], num_rows: usize, channels: usize) -> Pattern {
+    let mut cursor = 0;
+    let mut rows = Vec::with_capacity(num_rows);
+
+    for _ in 0..num_rows {
+        let mut row = Vec::with_capacity(channels);
+        for _ in 0..channels {
+            let mut event = NoteEvent::default();
+            if cursor >= data.len() {
+                row.push(event);
+                continue;
+            }
+
+            let first = data[cursor];
+            if first & 0x80 != 0 {
+                cursor += 1;
+                if first & 0x01 != 0 {
+                    event.note = *data.get(cursor).unwrap_or(&0);
+                    cursor += 1;
+                }
+                if first & 0x02 != 0 {
+                    cursor += 1; // instrument, unused by the stand-in synth
+                }
+                if first & 0x04 != 0 {
+                    event.volume = *data.get(cursor).unwrap_or(&0);
+                    cursor += 1;
+                }
+                if first & 0x08 != 0 {
+                    cursor += 1; // effect type, unused
+                }
+                if first & 0x10 != 0 {
+                    cursor += 1; // effect param, unused
+                }
+            } else {
+                event.note = first;
+                event.volume = *data.get(cursor + 2).unwrap_or(&0);
+                cursor += 5;
+            }
+
+            row.push(event);
+        }
+        rows.push(row);
+    }
+
+    Pattern { rows }
+}
+
+fn note_to_frequency(note: u8) -> f32 {
+    C0_FREQUENCY_HZ * 2f32.powf((note as f32 - 1.0) / 12.0)
+}
+
+/// Plays a `TrackerModule` through the default audio output and exposes its playback
+/// position so the render loop can use it as the visual clock.
+pub struct SoundPlayer {
+    position: Arc<Mutex<SongPosition>>,
+    _stream: cpal::Stream,
+}
+
+impl SoundPlayer {
+    /// Start playback of `module`, emitting a beat event (current BPM) on the
+    /// `beat_sender` channel on every row boundary, same as live beat detection.
+    pub fn play(module: TrackerModule, beat_sender: SyncSender<f64>) -> Result<SoundPlayer, SoundError> {
+        let host = cpal::default_host();
+        let device = host
+            .default_output_device()
+            .ok_or(SoundError::NoOutputDevice)?;
+        let config = device
+            .default_output_config()
+            .map_err(SoundError::Config)?;
+        let sample_rate = config.sample_rate().0;
+        let output_channels = config.channels() as usize;
+
+        let position = Arc::new(Mutex::new(SongPosition::default()));
+        let render_position = position.clone();
+
+        let mut player = XmPlayer::new(module, sample_rate);
+
+        let stream = device
+            .build_output_stream(
+                &config.into(),
+                move |data: &mut [f32], _: &cpal::OutputCallbackInfo| {
+                    for frame in data.chunks_mut(output_channels) {
+                        let sample = player.next_sample();
+                        for out in frame.iter_mut() {
+                            *out = sample;
+                        }
+
+                        if player.advance_row() {
+                            let pos = player.position();
+                            *render_position.lock().unwrap() = pos;
+                            let _ = beat_sender.try_send(pos.bpm as f64);
+                        }
+                    }
+                },
+                |err| println!("Sound playback error: {err}"),
+                None,
+            )
+            .map_err(SoundError::Build)?;
+
+        stream.play().map_err(SoundError::Play)?;
+
+        Ok(SoundPlayer {
+            position,
+            _stream: stream,
+        })
+    }
+
+    /// The most recently rendered playback position.
+    pub fn position(&self) -> SongPosition {
+        *self.position.lock().unwrap()
+    }
+
+    /// Seconds elapsed in the song, suitable for driving `u_time` directly.
+    pub fn time_seconds(&self) -> f64 {
+        self.position().elapsed_seconds
+    }
+}
+
+struct Channel {
+    frequency: Option<f32>,
+    phase: f32,
+    volume: f32,
+}
+
+/// Steps through `module`'s real order/pattern/row structure at its real tempo, and
+/// synthesizes a tone per active note rather than resampling instrument sample data.
+struct XmPlayer {
+    module: TrackerModule,
+    sample_rate: u32,
+    samples_per_row: f64,
+    sample_counter: f64,
+    order_index: usize,
+    row_index: usize,
+    channels: Vec<Channel>,
+    position: SongPosition,
+}
+
+impl XmPlayer {
+    fn new(module: TrackerModule, sample_rate: u32) -> XmPlayer {
+        let channels = (0..module.channels)
+            .map(|_| Channel {
+                frequency: None,
+                phase: 0.0,
+                volume: 0.0,
+            })
+            .collect();
+
+        let samples_per_tick = sample_rate as f64 * 2.5 / module.default_bpm as f64;
+        let samples_per_row = samples_per_tick * module.default_tempo as f64;
+
+        let mut player = XmPlayer {
+            sample_rate,
+            samples_per_row,
+            sample_counter: 0.0,
+            order_index: 0,
+            row_index: 0,
+            channels,
+            position: SongPosition {
+                tempo: module.default_tempo,
+                bpm: module.default_bpm,
+                ..Default::default()
+            },
+            module,
+        };
+        player.trigger_row();
+        player
+    }
+
+    fn current_pattern(&self) -> Option<&Pattern> {
+        let pattern_index = *self.module.order_table.get(self.order_index)? as usize;
+        self.module.patterns.get(pattern_index)
+    }
+
+    fn trigger_row(&mut self) {
+        let events: Vec<NoteEvent> = match self.current_pattern() {
+            Some(pattern) => pattern
+                .rows
+                .get(self.row_index)
+                .cloned()
+                .unwrap_or_default(),
+            None => return,
+        };
+
+        for (channel, event) in self.channels.iter_mut().zip(events.iter()) {
+            match event.note {
+                0 => (),
+                97 => channel.frequency = None,
+                note => {
+                    channel.frequency = Some(note_to_frequency(note));
+                    channel.volume = event.volume.min(64) as f32 / 64.0;
+                }
+            }
+        }
+    }
+
+    fn position(&self) -> SongPosition {
+        self.position
+    }
+
+    /// Mix one sample across all active channels (mono, duplicated to every output
+    /// channel by the caller).
+    fn next_sample(&mut self) -> f32 {
+        let active = self.channels.iter().filter(|c| c.frequency.is_some()).count();
+        if active == 0 {
+            return 0.0;
+        }
+
+        let mut sample = 0.0;
+        for channel in &mut self.channels {
+            if let Some(frequency) = channel.frequency {
+                sample += (channel.phase * std::f32::consts::TAU).sin() * channel.volume;
+                channel.phase = (channel.phase + frequency / self.sample_rate as f32).fract();
+            }
+        }
+
+        sample / active as f32 * 0.3
+    }
+
+    /// Advance one sample's worth of song time, returning `true` if a new row started.
+    fn advance_row(&mut self) -> bool {
+        self.sample_counter += 1.0;
+        self.position.elapsed_seconds += 1.0 / self.sample_rate as f64;
+
+        if self.sample_counter < self.samples_per_row {
+            return false;
+        }
+        self.sample_counter -= self.samples_per_row;
+
+        self.row_index += 1;
+        let pattern_len = self.current_pattern().map(|p| p.rows.len()).unwrap_or(0);
+        if self.row_index >= pattern_len {
+            self.row_index = 0;
+            self.order_index += 1;
+            if self.order_index >= self.module.song_length {
+                self.order_index = self.module.restart_position;
+            }
+        }
+
+        self.position.order = self.order_index as u32;
+        self.position.pattern = self
+            .module
+            .order_table
+            .get(self.order_index)
+            .copied()
+            .unwrap_or(0) as u32;
+        self.position.row = self.row_index as u32;
+
+        self.trigger_row();
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn module_with_pattern(rows: Vec<Vec<NoteEvent>>, channels: usize, tempo: u32, bpm: u32) -> TrackerModule {
+        TrackerModule {
+            channels,
+            default_tempo: tempo,
+            default_bpm: bpm,
+            song_length: 1,
+            restart_position: 0,
+            order_table: vec![0],
+            patterns: vec![Pattern { rows }],
+        }
+    }
+
+    #[test]
+    fn parse_pattern_unpacks_the_uncompressed_five_byte_form() {
+        // High bit clear means all five fields are present unconditionally: note,
+        // instrument, volume, effect, param.
+        let data = [40u8, 0, 60, 0, 0];
+        let pattern = parse_pattern(&data, 1, 1);
+        assert_eq!(pattern.rows[0][0].note, 40);
+        assert_eq!(pattern.rows[0][0].volume, 60);
+    }
+
+    #[test]
+    fn parse_pattern_unpacks_the_compressed_form_honoring_the_field_mask() {
+        // High bit set: only note (bit 0) and volume (bit 2) follow.
+        let data = [0x80 | 0x01 | 0x04, 33, 50];
+        let pattern = parse_pattern(&data, 1, 1);
+        assert_eq!(pattern.rows[0][0].note, 33);
+        assert_eq!(pattern.rows[0][0].volume, 50);
+    }
+
+    #[test]
+    fn parse_pattern_skips_fields_absent_from_the_compression_mask() {
+        // Only the volume bit is set, so note stays at its NoteEvent::default() of 0.
+        let data = [0x80 | 0x04, 50];
+        let pattern = parse_pattern(&data, 1, 1);
+        assert_eq!(pattern.rows[0][0].note, 0);
+        assert_eq!(pattern.rows[0][0].volume, 50);
+    }
+
+    #[test]
+    fn parse_pattern_defaults_rows_truncated_by_a_short_buffer() {
+        let pattern = parse_pattern(&[], 2, 1);
+        assert_eq!(pattern.rows.len(), 2);
+        assert_eq!(pattern.rows[0][0].note, 0);
+        assert_eq!(pattern.rows[1][0].note, 0);
+    }
+
+    #[test]
+    fn note_to_frequency_is_an_octave_per_twelve_notes() {
+        assert_eq!(note_to_frequency(1), C0_FREQUENCY_HZ);
+        assert!((note_to_frequency(13) - C0_FREQUENCY_HZ * 2.0).abs() < 1e-4);
+    }
+
+    #[test]
+    fn samples_per_row_follows_the_xm_tempo_formula() {
+        // XM ticks a row every `tempo` ticks, and a tick is 2.5/bpm seconds.
+        let module = module_with_pattern(vec![vec![NoteEvent::default()]], 1, 6, 125);
+        let player = XmPlayer::new(module, 44_100);
+        let expected = 44_100.0 * 2.5 / 125.0 * 6.0;
+        assert_eq!(player.samples_per_row, expected);
+    }
+
+    #[test]
+    fn advance_row_wraps_to_the_first_row_at_the_pattern_end() {
+        let module = module_with_pattern(
+            vec![vec![NoteEvent::default()], vec![NoteEvent::default()]],
+            1,
+            1,
+            125,
+        );
+        let mut player = XmPlayer::new(module, 44_100);
+        assert_eq!(player.position().row, 0);
+
+        // Drive past the first row boundary.
+        while !player.advance_row() {}
+        assert_eq!(player.position().row, 1);
+
+        // Drive past the pattern's last row: row wraps back to 0, and since
+        // song_length is 1 the single order also wraps back to restart_position.
+        while !player.advance_row() {}
+        assert_eq!(player.position().row, 0);
+        assert_eq!(player.position().order, 0);
+    }
+}